@@ -0,0 +1,171 @@
+use anyhow::{bail, Result};
+
+use crate::mapper::{Mapper, NullMapper};
+
+// reference: https://www.nesdev.org/wiki/CPU_memory_map
+
+/// Size in bytes of the internal 2KB work RAM, before mirroring.
+const INTERNAL_RAM_SIZE: usize = 0x0800;
+/// Number of PPU registers exposed to the CPU, before mirroring.
+const PPU_REGISTER_COUNT: usize = 8;
+/// Size in bytes of the APU/IO register block.
+const APU_IO_SIZE: usize = 0x20;
+
+/// A device window on the CPU bus, described by the address range it
+/// claims and the mask applied before indexing its backing storage.
+///
+/// Mirrored regions (internal RAM, PPU registers) are modelled by giving
+/// the port a mask narrower than the region it claims, so every address
+/// in the region collapses onto the same handful of bits.
+#[derive(Debug)]
+struct BusPort {
+    base: u16,
+    top: u16,
+    mask: u16,
+}
+
+impl BusPort {
+    const fn new(base: u16, top: u16, mask: u16) -> Self {
+        Self { base, top, mask }
+    }
+
+    fn contains(&self, addr: u16) -> bool {
+        addr >= self.base && addr <= self.top
+    }
+
+    fn offset(&self, addr: u16) -> usize {
+        ((addr - self.base) & self.mask) as usize
+    }
+}
+
+const RAM_PORT: BusPort = BusPort::new(0x0000, 0x1fff, 0b0000_0111_1111_1111);
+const PPU_PORT: BusPort = BusPort::new(0x2000, 0x3fff, 0b0000_0000_0000_0111);
+const APU_IO_PORT: BusPort = BusPort::new(0x4000, 0x401f, 0b0000_0000_0001_1111);
+
+/// Routes CPU-visible addresses to the devices that make up a real NES:
+/// internal RAM, PPU registers, the APU/IO register block, and cartridge
+/// space (handled by a [`Mapper`]).
+pub trait Bus: std::fmt::Debug {
+    fn mem_read(&self, addr: u16) -> Result<u8>;
+    fn mem_write(&mut self, addr: u16, data: u8) -> Result<()>;
+
+    /// Serialize the full bus (RAM, stubbed PPU/APU registers, and the
+    /// mapper's own state) for a save-state snapshot.
+    fn save_state(&self) -> Vec<u8>;
+
+    /// Restore state produced by `save_state`.
+    fn load_state(&mut self, bytes: &[u8]) -> Result<()>;
+
+    /// Cartridge PRG RAM (`$6000-$7FFF`), for battery-backed `.sav` files.
+    fn prg_ram(&self) -> Option<&[u8]>;
+
+    /// Mutable counterpart of `prg_ram`, used to restore a loaded `.sav`.
+    fn prg_ram_mut(&mut self) -> Option<&mut [u8]>;
+}
+
+#[derive(Debug)]
+pub struct NesBus {
+    /// 2KB of internal work RAM, mirrored four times across `$0000-$1FFF`.
+    ram: [u8; INTERNAL_RAM_SIZE],
+    /// PPU registers, mirrored every 8 bytes across `$2000-$3FFF`.
+    ///
+    /// The PPU itself isn't implemented yet, so this is just a flat
+    /// register file for now.
+    ppu_registers: [u8; PPU_REGISTER_COUNT],
+    /// APU and IO registers, `$4000-$401F`.
+    ///
+    /// The APU itself isn't implemented yet, so this is just a flat
+    /// register file for now.
+    apu_io: [u8; APU_IO_SIZE],
+    /// Cartridge space, `$4020-$FFFF`, routed through the mapper.
+    mapper: Box<dyn Mapper>,
+}
+
+impl Default for NesBus {
+    fn default() -> Self {
+        Self::new(Box::new(NullMapper::default()))
+    }
+}
+
+impl NesBus {
+    pub fn new(mapper: Box<dyn Mapper>) -> Self {
+        Self {
+            ram: [0u8; INTERNAL_RAM_SIZE],
+            ppu_registers: [0u8; PPU_REGISTER_COUNT],
+            apu_io: [0u8; APU_IO_SIZE],
+            mapper,
+        }
+    }
+
+    pub fn set_mapper(&mut self, mapper: Box<dyn Mapper>) {
+        self.mapper = mapper;
+    }
+}
+
+impl Bus for NesBus {
+    fn mem_read(&self, addr: u16) -> Result<u8> {
+        if RAM_PORT.contains(addr) {
+            Ok(self.ram[RAM_PORT.offset(addr)])
+        } else if PPU_PORT.contains(addr) {
+            Ok(self.ppu_registers[PPU_PORT.offset(addr)])
+        } else if APU_IO_PORT.contains(addr) {
+            Ok(self.apu_io[APU_IO_PORT.offset(addr)])
+        } else {
+            self.mapper.mem_read(addr)
+        }
+    }
+
+    fn mem_write(&mut self, addr: u16, data: u8) -> Result<()> {
+        if RAM_PORT.contains(addr) {
+            self.ram[RAM_PORT.offset(addr)] = data;
+        } else if PPU_PORT.contains(addr) {
+            self.ppu_registers[PPU_PORT.offset(addr)] = data;
+        } else if APU_IO_PORT.contains(addr) {
+            self.apu_io[APU_IO_PORT.offset(addr)] = data;
+        } else {
+            self.mapper.mem_write(addr, data)?;
+        }
+        Ok(())
+    }
+
+    fn save_state(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(
+            INTERNAL_RAM_SIZE + PPU_REGISTER_COUNT + APU_IO_SIZE,
+        );
+        out.extend_from_slice(&self.ram);
+        out.extend_from_slice(&self.ppu_registers);
+        out.extend_from_slice(&self.apu_io);
+        out.extend_from_slice(&self.mapper.save_state());
+        out
+    }
+
+    fn load_state(&mut self, bytes: &[u8]) -> Result<()> {
+        let fixed_size = INTERNAL_RAM_SIZE + PPU_REGISTER_COUNT + APU_IO_SIZE;
+        if bytes.len() < fixed_size {
+            bail!(
+                "corrupt save state: expected at least {} bytes of bus state, got {}",
+                fixed_size,
+                bytes.len()
+            );
+        }
+
+        let mut offset = 0;
+        self.ram.copy_from_slice(&bytes[offset..offset + INTERNAL_RAM_SIZE]);
+        offset += INTERNAL_RAM_SIZE;
+        self.ppu_registers
+            .copy_from_slice(&bytes[offset..offset + PPU_REGISTER_COUNT]);
+        offset += PPU_REGISTER_COUNT;
+        self.apu_io
+            .copy_from_slice(&bytes[offset..offset + APU_IO_SIZE]);
+        offset += APU_IO_SIZE;
+        self.mapper.load_state(&bytes[offset..])
+    }
+
+    fn prg_ram(&self) -> Option<&[u8]> {
+        self.mapper.prg_ram()
+    }
+
+    fn prg_ram_mut(&mut self) -> Option<&mut [u8]> {
+        self.mapper.prg_ram_mut()
+    }
+}
@@ -0,0 +1,182 @@
+use std::fs;
+use std::path::Path;
+
+use anyhow::{bail, Result};
+
+use crate::mapper::Mapper;
+
+// reference: https://www.nesdev.org/wiki/INES
+
+const INES_MAGIC: [u8; 4] = [0x4e, 0x45, 0x53, 0x1a]; // "NES\x1A"
+const HEADER_SIZE: usize = 16;
+const TRAINER_SIZE: usize = 512;
+const PRG_ROM_BANK_SIZE: usize = 0x4000; // 16KB
+const CHR_ROM_BANK_SIZE: usize = 0x2000; // 8KB
+/// PRG ROM/RAM window the CPU sees the cartridge through ($8000-$FFFF).
+const PRG_ROM_WINDOW: usize = 0x8000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mirroring {
+    Horizontal,
+    Vertical,
+    FourScreen,
+}
+
+/// Parsed iNES header (the first 16 bytes of a `.nes` file).
+#[derive(Debug)]
+pub struct INesHeader {
+    pub prg_rom_banks: usize,
+    pub chr_rom_banks: usize,
+    pub mapper_number: u8,
+    pub mirroring: Mirroring,
+    pub has_battery: bool,
+    pub has_trainer: bool,
+}
+
+impl INesHeader {
+    fn parse(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() < HEADER_SIZE || bytes[0..4] != INES_MAGIC {
+            bail!("not an iNES image: missing \"NES\\x1A\" magic");
+        }
+        if bytes[4] == 0 {
+            bail!("invalid iNES image: header declares zero PRG ROM banks");
+        }
+        let flags6 = bytes[6];
+        let flags7 = bytes[7];
+        let mirroring = if flags6 & 0b0000_1000 != 0 {
+            Mirroring::FourScreen
+        } else if flags6 & 0b0000_0001 != 0 {
+            Mirroring::Vertical
+        } else {
+            Mirroring::Horizontal
+        };
+        Ok(Self {
+            prg_rom_banks: bytes[4] as usize,
+            chr_rom_banks: bytes[5] as usize,
+            mapper_number: (flags7 & 0b1111_0000) | (flags6 >> 4),
+            mirroring,
+            has_battery: flags6 & 0b0000_0010 != 0,
+            has_trainer: flags6 & 0b0000_0100 != 0,
+        })
+    }
+}
+
+/// A parsed `.nes` ROM image: header plus the raw PRG/CHR banks, not yet
+/// wired up to a [`Mapper`].
+#[derive(Debug)]
+pub struct Cartridge {
+    pub header: INesHeader,
+    pub prg_rom: Vec<u8>,
+    pub chr_rom: Vec<u8>,
+}
+
+impl Cartridge {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let header = INesHeader::parse(bytes)?;
+
+        let mut offset = HEADER_SIZE;
+        if header.has_trainer {
+            offset += TRAINER_SIZE;
+        }
+
+        let prg_size = header.prg_rom_banks * PRG_ROM_BANK_SIZE;
+        if bytes.len() < offset + prg_size {
+            bail!("truncated iNES image: PRG ROM runs past end of file");
+        }
+        let prg_rom = bytes[offset..offset + prg_size].to_vec();
+        offset += prg_size;
+
+        let chr_size = header.chr_rom_banks * CHR_ROM_BANK_SIZE;
+        if bytes.len() < offset + chr_size {
+            bail!("truncated iNES image: CHR ROM runs past end of file");
+        }
+        let chr_rom = bytes[offset..offset + chr_size].to_vec();
+
+        Ok(Self {
+            header,
+            prg_rom,
+            chr_rom,
+        })
+    }
+
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let bytes = fs::read(path)?;
+        Self::from_bytes(&bytes)
+    }
+
+    /// Build the mapper this cartridge's header asks for.
+    ///
+    /// Only mapper 0 (NROM) is supported so far; anything else comes back
+    /// as an error until a mapper is added for it.
+    pub fn build_mapper(self) -> Result<Box<dyn Mapper>> {
+        match self.header.mapper_number {
+            0 => Ok(Box::new(NromMapper::new(self.prg_rom, self.chr_rom))),
+            n => bail!("unsupported mapper number {n}"),
+        }
+    }
+}
+
+/// Mapper 0 (NROM): no bank switching. A 16KB PRG bank is mirrored into
+/// both halves of the `$8000-$FFFF` window; a 32KB bank fills it directly.
+/// Backed by a fixed 8KB of PRG RAM at `$6000-$7FFF`.
+#[derive(Debug)]
+pub struct NromMapper {
+    prg_rom: Vec<u8>,
+    #[allow(dead_code)]
+    chr_rom: Vec<u8>,
+    prg_ram: [u8; 0x2000],
+}
+
+impl NromMapper {
+    pub fn new(prg_rom: Vec<u8>, chr_rom: Vec<u8>) -> Self {
+        Self {
+            prg_rom,
+            chr_rom,
+            prg_ram: [0u8; 0x2000],
+        }
+    }
+}
+
+impl Mapper for NromMapper {
+    fn mem_read(&self, addr: u16) -> Result<u8> {
+        match addr as usize {
+            0x6000..=0x7fff => Ok(self.prg_ram[addr as usize - 0x6000]),
+            PRG_ROM_WINDOW..=0xffff => {
+                let offset = (addr as usize - PRG_ROM_WINDOW) % self.prg_rom.len();
+                Ok(self.prg_rom[offset])
+            }
+            _ => Ok(0),
+        }
+    }
+
+    fn mem_write(&mut self, addr: u16, data: u8) -> Result<()> {
+        if let 0x6000..=0x7fff = addr as usize {
+            self.prg_ram[addr as usize - 0x6000] = data;
+        }
+        Ok(())
+    }
+
+    fn save_state(&self) -> Vec<u8> {
+        self.prg_ram.to_vec()
+    }
+
+    fn load_state(&mut self, bytes: &[u8]) -> Result<()> {
+        if bytes.len() != self.prg_ram.len() {
+            bail!(
+                "corrupt save state: expected {} bytes of PRG RAM, got {}",
+                self.prg_ram.len(),
+                bytes.len()
+            );
+        }
+        self.prg_ram.copy_from_slice(bytes);
+        Ok(())
+    }
+
+    fn prg_ram(&self) -> Option<&[u8]> {
+        Some(&self.prg_ram)
+    }
+
+    fn prg_ram_mut(&mut self) -> Option<&mut [u8]> {
+        Some(&mut self.prg_ram)
+    }
+}
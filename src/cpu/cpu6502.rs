@@ -1,19 +1,23 @@
-use std::fs::File;
-use std::io::BufReader;
+use std::fs;
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
 
-use anyhow::{Error, Result};
+use anyhow::{bail, Error, Result};
 use structopt::StructOpt;
 
+use crate::bus::{Bus, NesBus};
+use crate::cartridge::Cartridge;
 use crate::cli::Cli;
 use crate::constant::ADDRESS_BRK;
 use crate::constant::ADDRESS_TEST_PROGRAM;
-use crate::constant::MEMORY_MAX;
 use crate::constant::NEGATIVE_FLAG;
 use crate::constant::PC_ADDRESS_RESET;
 use crate::constant::PRG_ROM_ADDRESS;
 use crate::cpu::debugger::CpuDebugger;
 use crate::cpu::instruction::CpuInstruction;
-use crate::cpu::opcode::{Operation, OPCODE_TABLE};
+use crate::cpu::opcode::{AddressingMode, Operation, OPCODE_TABLE};
+use crate::cpu::variant::{Nmos6502, Variant};
+use crate::interrupt::Interrupt;
 use crate::mem::Mem;
 use crate::stack::get_sp_offset;
 use crate::stack::Stacked;
@@ -22,26 +26,77 @@ use super::CpuRegister;
 
 // reference: https://www.nesdev.org/wiki/CPU_registers
 
+/// Cycles charged for servicing an NMI, IRQ, BRK, or RESET.
+const INTERRUPT_CYCLES: u8 = 7;
+
+/// Version tag prefixed to every `save_state` blob, bumped whenever the
+/// snapshot layout changes so stale saves fail loudly instead of silently
+/// corrupting machine state.
+const SAVE_STATE_VERSION: u8 = 1;
+
+/// The CPU, generic over the [`Variant`] (NMOS 6502 vs 65C02) whose opcode
+/// set it decodes and executes. Defaults to [`Nmos6502`], the chip the NES
+/// actually shipped with.
 #[derive(Debug)]
-pub struct Cpu6502 {
+pub struct Cpu6502<V: Variant = Nmos6502> {
     pub debugger: CpuDebugger<u8>,
     pub clocks_to_pause: u8,
     pub registers: CpuRegister,
-    /// NES memory uses 16-bit for memory addressing
+    /// NES memory uses 16-bit for memory addressing.
     /// The stack address space is hardwired to memory page $01, i.e. the address range $0100–$01FF (256–511)
-    pub mapper: [u8; MEMORY_MAX], // 64KB
+    ///
+    /// Routes reads/writes to internal RAM, PPU/APU registers, and cartridge
+    /// space instead of a single flat array, so the CPU is reusable against
+    /// real NES memory maps.
+    pub bus: Box<dyn Bus>,
     pub instr: Option<CpuInstruction>, // The currently executing instruction
+    /// Set by [`Cpu6502::request_interrupt`] and serviced at the top of the
+    /// next `clocked` call. NMI is unmaskable and is serviced even if
+    /// `interrupt_disabled` is set; a pending IRQ waits until that flag is
+    /// cleared.
+    pub pending_interrupt: Option<Interrupt>,
+    /// Path of the currently loaded ROM, kept around so a battery-backed
+    /// cartridge's PRG RAM can be flushed to a sibling `.sav` file on drop.
+    rom_path: Option<PathBuf>,
+    /// Whether the loaded cartridge's battery flag (iNES flags 6 bit 1)
+    /// was set, i.e. whether PRG RAM should survive between runs.
+    battery_backed: bool,
+    /// Running total of cycles executed, incremented by `instr.cycle` each
+    /// step and by `INTERRUPT_CYCLES` for each serviced interrupt. Extra
+    /// cycles (`instr.extra_cycle`, e.g. for page crossings) are not
+    /// counted. Feeds the `CYC:n` field of the nestest-format trace.
+    pub cycles: u64,
+    /// Called with a formatted nestest-style trace line (see
+    /// `nestest_trace_line`) before each instruction executes, so a
+    /// headless test harness can compare the log against a reference
+    /// ROM's output.
+    pub trace: Option<fn(&str)>,
+    variant: PhantomData<V>,
 }
 
-impl Default for Cpu6502 {
+impl<V: Variant> Default for Cpu6502<V> {
     fn default() -> Self {
         let debugger = CpuDebugger::default();
         Self {
             debugger,
             clocks_to_pause: 0,
             registers: CpuRegister::default(),
-            mapper: [0u8; MEMORY_MAX],
+            bus: Box::new(NesBus::default()),
             instr: None,
+            pending_interrupt: None,
+            rom_path: None,
+            battery_backed: false,
+            cycles: 0,
+            trace: None,
+            variant: PhantomData,
+        }
+    }
+}
+
+impl<V: Variant> Drop for Cpu6502<V> {
+    fn drop(&mut self) {
+        if self.battery_backed {
+            let _ = self.flush_battery_ram();
         }
     }
 }
@@ -50,8 +105,16 @@ pub trait Clocked {
     fn clocked(self: &mut Self) -> Result<bool>;
 }
 
-impl Clocked for Cpu6502 {
+impl<V: Variant> Clocked for Cpu6502<V> {
     fn clocked(self: &mut Self) -> Result<bool> {
+        if let Some(interrupt) = self.pending_interrupt {
+            if !interrupt.is_maskable() || !self.registers.interrupt_disabled {
+                self.pending_interrupt = None;
+                self.handle_interrupt(interrupt)?;
+                return Ok(true);
+            }
+        }
+
         // // load cpu program counter register at $8000
         if let Ok(opcode) = self.mem_read(self.registers.pc) {
             let (addr, addr_value, num_bytes, mut instr) =
@@ -60,23 +123,34 @@ impl Clocked for Cpu6502 {
             instr.mode_args = addr_value;
             instr.write_target = addr;
 
-            if instr.opcode == Operation::BRK {
-                self.debugger.debug_instr(self, instr);
-                return Ok(false);
-            }
-
             self.instr = Some(instr);
 
-            // Debug the instruction
-            self.debugger.debug_instr(self, instr);
+            if let Some(trace) = self.trace {
+                trace(&self.nestest_trace_line(&instr, num_bytes));
+            }
 
-            println!("Program counter {:0x?}", self.registers.pc);
+            // Debug the instruction
+            self.debugger.debug_instr(self, instr)?;
 
             self.registers.pc = self.registers.pc.wrapping_add(num_bytes);
-            self.execute_instruction(&instr)?;
 
-            println!("After => Program counter {:0x?}", self.registers.pc);
+            if instr.opcode == Operation::BRK {
+                // BRK has a padding/signature byte after the opcode, so
+                // real hardware pushes PC+2 (not the PC+1 that `num_bytes`
+                // already advanced to), letting RTI return past it instead
+                // of into the middle of the instruction.
+                self.registers.pc = self.registers.pc.wrapping_add(1);
+                // `handle_interrupt` already charges INTERRUPT_CYCLES to
+                // `cycles`/`clocks_to_pause`, so return here instead of
+                // falling through to the generic `instr.cycle` accounting
+                // below, which would double-charge BRK's cycles.
+                self.handle_interrupt(Interrupt::Brk)?;
+                return Ok(true);
+            }
 
+            self.execute_instruction(&instr)?;
+
+            self.cycles = self.cycles.wrapping_add(instr.cycle as u64);
             self.clocks_to_pause = self.clocks_to_pause.wrapping_add(instr.cycle - 1);
             return Ok(true);
         }
@@ -84,7 +158,7 @@ impl Clocked for Cpu6502 {
     }
 }
 
-impl Stacked for Cpu6502 {
+impl<V: Variant> Stacked for Cpu6502<V> {
     #[must_use]
     #[inline]
     fn push_stack(&mut self, val: u8) -> Result<()> {
@@ -104,26 +178,18 @@ impl Stacked for Cpu6502 {
     }
 }
 
-impl Mem for Cpu6502 {
+impl<V: Variant> Mem for Cpu6502<V> {
     fn mem_read(&self, addr: u16) -> Result<u8> {
-        match addr {
-            0x0000..=0x1fff => {
-                // Mask to zero out the highest two bits in a 16-bit address
-                let mirror_down_addr = addr & 0b00000111_11111111;
-                println!("Read from address {:0x?}", mirror_down_addr);
-                Ok(self.mapper[mirror_down_addr as usize])
-            }
-            _ => Ok(self.mapper[addr as usize]),
-        }
+        self.bus.mem_read(addr)
     }
 
     fn mem_write(&mut self, addr: u16, data: u8) -> Result<()> {
-        self.mapper[addr as usize] = data;
-        Ok(())
+        self.debugger.note_write(addr);
+        self.bus.mem_write(addr, data)
     }
 }
 
-impl Cpu6502 {
+impl<V: Variant> Cpu6502<V> {
     // memory
     pub fn read_write_target(&self, write_target: Option<u16>) -> Result<u8> {
         Ok(match write_target {
@@ -170,7 +236,6 @@ impl Cpu6502 {
         println!("Zero: {:?}", self.registers.zero);
     }
 
-    #[allow(unused)]
     pub fn set_status_register_from_byte(&mut self, v: u8) {
         // N.O._._.D.I.Z.C
         self.registers.carry = v & 0b00000001 > 0;
@@ -183,19 +248,54 @@ impl Cpu6502 {
         self.registers.negative = v & 0b10000000 > 0;
     }
 
-    #[allow(unused)]
     pub fn status_register_byte(&self, is_instruction: bool) -> u8 {
         let result = ((self.registers.carry      as u8) << 0) |
             ((self.registers.zero       as u8) << 1) |
             ((self.registers.interrupt_disabled as u8) << 2) |
             ((self.registers.decimal    as u8) << 3) |
-            (0                       << 4) | // Break flag
-            ((if is_instruction {1} else {0}) << 5) |
+            ((if is_instruction {1} else {0}) << 4) | // Break flag: set for BRK/PHP, clear for IRQ/NMI
+            (1                       << 5) | // Bit 5 is unused but always reads back as 1
             ((self.registers.overflow   as u8) << 6) |
             ((self.registers.negative   as u8) << 7);
         return result;
     }
 
+    /// Queue an interrupt to be serviced at the top of the next `clocked`
+    /// call. NMI and RESET should be requested this way; BRK is handled
+    /// directly by `clocked` when it decodes the opcode.
+    pub fn request_interrupt(&mut self, interrupt: Interrupt) {
+        self.pending_interrupt = Some(interrupt);
+    }
+
+    /// Push PC and status, mask IRQs, and load PC from the interrupt's
+    /// vector. The B flag in the pushed status is set only for BRK, per
+    /// the real CPU's behavior. On 65C02, BRK also clears the decimal
+    /// flag.
+    fn handle_interrupt(&mut self, interrupt: Interrupt) -> Result<()> {
+        // RESET doesn't write anything to the stack; real hardware only
+        // walks the stack pointer down by 3 (as if pushing, without
+        // driving the data bus). Pushing PC/status here would corrupt
+        // whatever the stack held across the reset.
+        if interrupt == Interrupt::Reset {
+            self.registers.sp = self.registers.sp.wrapping_sub(3);
+        } else {
+            self.push_stack16(self.registers.pc)?;
+            let is_brk = interrupt == Interrupt::Brk;
+            self.push_stack(self.status_register_byte(is_brk))?;
+            if is_brk && V::IS_CMOS {
+                self.registers.decimal = false;
+            }
+        }
+        self.registers.interrupt_disabled = true;
+
+        self.registers.pc = self.mem_read_u16(interrupt.vector_address())?;
+        self.cycles = self.cycles.wrapping_add(INTERRUPT_CYCLES as u64);
+        self.clocks_to_pause = self
+            .clocks_to_pause
+            .wrapping_add(INTERRUPT_CYCLES - 1);
+        Ok(())
+    }
+
     pub fn reset(&mut self) -> Result<()> {
         self.instr = None;
 
@@ -203,15 +303,144 @@ impl Cpu6502 {
         self.registers.x = 0;
         // // Reset the address of program counter
         self.registers.pc = self.mem_read_u16(PC_ADDRESS_RESET).unwrap();
+        // RESET itself takes 7 cycles on real hardware, so the first
+        // instruction's trace line reads CYC:7, matching nestest's logs.
+        self.cycles = INTERRUPT_CYCLES as u64;
         Ok(())
     }
 
+    /// Load an iNES (`.nes`) ROM image from disk, build the mapper its
+    /// header asks for, and reset the CPU so the program counter is taken
+    /// from the reset vector at $FFFC.
+    ///
+    /// If the cartridge is battery-backed and a sibling `.sav` file exists
+    /// next to it, its PRG RAM is loaded back in before reset.
+    pub fn load_rom<P: AsRef<Path>>(self: &mut Self, path: P) -> Result<()> {
+        let path = path.as_ref().to_path_buf();
+        let cartridge = Cartridge::from_file(&path)?;
+        let battery_backed = cartridge.header.has_battery;
+        let mapper = cartridge.build_mapper()?;
+
+        self.bus = Box::new(NesBus::new(mapper));
+        self.rom_path = Some(path);
+        self.battery_backed = battery_backed;
+
+        if self.battery_backed {
+            self.load_battery_ram()?;
+        }
+
+        self.reset()
+    }
+
+    /// Sibling `<rom>.sav` path for the currently loaded ROM, if any.
+    fn sav_path(&self) -> Option<PathBuf> {
+        self.rom_path.as_ref().map(|path| path.with_extension("sav"))
+    }
+
+    /// Load a previously saved `.sav` file's PRG RAM, if one exists.
+    fn load_battery_ram(&mut self) -> Result<()> {
+        let Some(sav_path) = self.sav_path() else {
+            return Ok(());
+        };
+        if !sav_path.exists() {
+            return Ok(());
+        }
+        let saved = fs::read(sav_path)?;
+        if let Some(prg_ram) = self.bus.prg_ram_mut() {
+            if saved.len() != prg_ram.len() {
+                bail!(
+                    "corrupt .sav file: expected {} bytes of PRG RAM, got {}",
+                    prg_ram.len(),
+                    saved.len()
+                );
+            }
+            prg_ram.copy_from_slice(&saved);
+        }
+        Ok(())
+    }
+
+    /// Write the cartridge's PRG RAM (`$6000-$7FFF`) out to its `.sav`
+    /// file, for battery-backed saves to survive between runs.
+    pub fn flush_battery_ram(&self) -> Result<()> {
+        let Some(sav_path) = self.sav_path() else {
+            return Ok(());
+        };
+        if let Some(prg_ram) = self.bus.prg_ram() {
+            fs::write(sav_path, prg_ram)?;
+        }
+        Ok(())
+    }
+
+    /// Serialize the full machine state (registers, clocks-to-pause, and
+    /// bus/memory contents) into a versioned snapshot.
+    pub fn save_state(&self) -> Vec<u8> {
+        let mut out = vec![SAVE_STATE_VERSION];
+        out.push(self.registers.a);
+        out.push(self.registers.x);
+        out.push(self.registers.y);
+        out.push(self.registers.sp);
+        out.extend_from_slice(&self.registers.pc.to_le_bytes());
+        out.push(self.status_register_byte(false));
+        out.push(self.clocks_to_pause);
+        out.extend_from_slice(&self.bus.save_state());
+        out
+    }
+
+    /// Restore a snapshot produced by `save_state`.
+    ///
+    /// `instr` (the currently-executing-instruction cache) is transient
+    /// per-tick debug state, not architectural state, so it isn't part of
+    /// the snapshot; it's simply cleared and re-derived by the next tick.
+    pub fn load_state(&mut self, bytes: &[u8]) -> Result<()> {
+        let Some((&version, rest)) = bytes.split_first() else {
+            bail!("empty save state");
+        };
+        if version != SAVE_STATE_VERSION {
+            bail!("unsupported save state version {version}");
+        }
+
+        if rest.len() < 6 {
+            bail!("truncated save state: missing register header");
+        }
+        let (header, bus_bytes) = rest.split_at(6);
+        self.registers.a = header[0];
+        self.registers.x = header[1];
+        self.registers.y = header[2];
+        self.registers.sp = header[3];
+        self.registers.pc = u16::from_le_bytes([header[4], header[5]]);
+
+        let Some((&status, rest)) = bus_bytes.split_first() else {
+            bail!("truncated save state: missing status byte");
+        };
+        self.set_status_register_from_byte(status);
+
+        let Some((&clocks_to_pause, bus_bytes)) = rest.split_first() else {
+            bail!("truncated save state: missing clocks_to_pause byte");
+        };
+        self.clocks_to_pause = clocks_to_pause;
+
+        self.bus.load_state(bus_bytes)?;
+        self.instr = None;
+        Ok(())
+    }
+
+    pub fn save_state_to<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        fs::write(path, self.save_state())?;
+        Ok(())
+    }
+
+    pub fn load_state_from<P: AsRef<Path>>(&mut self, path: P) -> Result<()> {
+        let bytes = fs::read(path)?;
+        self.load_state(&bytes)
+    }
+
     #[allow(unused)]
     pub fn load_program(self: &mut Self, program: Vec<u8>) -> Result<()> {
         // $8000–$FFFF: ROM and mapper registers ((see MMC1 and UxROM for examples))
-        let program_rom_address = PRG_ROM_ADDRESS as usize;
-        self.mapper[program_rom_address..(program_rom_address + program.len())]
-            .copy_from_slice(&program[..]);
+        let program_rom_address = PRG_ROM_ADDRESS;
+        for (offset, byte) in program.iter().enumerate() {
+            self.mem_write(program_rom_address.wrapping_add(offset as u16), *byte)?;
+        }
 
         // Write the value of program counter as the start address of PRG ROM
         self.mem_write_u16(PC_ADDRESS_RESET, PRG_ROM_ADDRESS)
@@ -229,9 +458,10 @@ impl Cpu6502 {
     }
 
     pub fn load_test_program(self: &mut Self, program: Vec<u8>) -> Result<()> {
-        let program_rom_address = PRG_ROM_ADDRESS as usize;
-        self.mapper[program_rom_address..(program_rom_address + program.len())]
-            .copy_from_slice(&program[..]);
+        let program_rom_address = PRG_ROM_ADDRESS;
+        for (offset, byte) in program.iter().enumerate() {
+            self.mem_write(program_rom_address.wrapping_add(offset as u16), *byte)?;
+        }
 
         // Write the value of program counter as the start address of PRG ROM
         self.mem_write_u16(ADDRESS_TEST_PROGRAM, PRG_ROM_ADDRESS)
@@ -289,12 +519,28 @@ impl Cpu6502 {
     }
 
     fn execute_instruction(self: &mut Self, instruction: &CpuInstruction) -> Result<(), Error> {
+        // The 65C02 reuses the NMOS `BIT`/`INC`/`DEC` opcodes for its new
+        // immediate-mode `BIT` and accumulator `INC A`/`DEC A` forms, which
+        // decode with no `write_target` (there's no memory operand). The
+        // NMOS handlers for these always unwrap `write_target`, so route
+        // these variants to dedicated handlers before the generic dispatch
+        // below reaches them.
+        if V::IS_CMOS && instruction.write_target.is_none() {
+            match instruction.opcode {
+                Operation::BIT => return self.BIT_IMMEDIATE(),
+                Operation::INC => return self.INC_A(),
+                Operation::DEC => return self.DEC_A(),
+                _ => {}
+            }
+        }
+
         macro_rules! execute_opcode {
             ($($opcode:ident),*) => {
                 match instruction.opcode {
                     $(
                         Operation::$opcode => self.$opcode(),
                     )*
+                    _ if V::IS_CMOS => self.execute_cmos_opcode(instruction),
                     _ => unimplemented!()
                 }
             };
@@ -317,13 +563,181 @@ impl Cpu6502 {
         );
     }
 
-    /// Read image from a provided input path
+    /// Dispatch the 65C02 opcode extensions. Only reached when `V::IS_CMOS`
+    /// is true; opcodes this match doesn't recognize are treated as
+    /// single-cycle NOPs rather than `unimplemented!()`, since CMOS parts
+    /// guarantee their unused opcodes are harmless no-ops.
+    fn execute_cmos_opcode(self: &mut Self, instruction: &CpuInstruction) -> Result<(), Error> {
+        match instruction.opcode {
+            Operation::STZ => self.STZ(),
+            Operation::TRB => self.TRB(),
+            Operation::TSB => self.TSB(),
+            Operation::BRA => self.BRA(),
+            Operation::PHX => self.PHX(),
+            Operation::PHY => self.PHY(),
+            Operation::PLX => self.PLX(),
+            Operation::PLY => self.PLY(),
+            _ => Ok(()),
+        }
+    }
+
+    /// CMOS: store zero to the addressed memory location.
+    #[allow(non_snake_case)]
+    fn STZ(&mut self) -> Result<()> {
+        let instr = self.instr.unwrap();
+        self.mem_write(instr.write_target.unwrap(), 0)
+    }
+
+    /// CMOS: test-and-reset bits. Z is set from `A & M`, then the bits set
+    /// in A are cleared in M.
+    #[allow(non_snake_case)]
+    fn TRB(&mut self) -> Result<()> {
+        let instr = self.instr.unwrap();
+        let addr = instr.write_target.unwrap();
+        let m = self.mem_read(addr)?;
+        self.registers.zero = (self.registers.a & m) == 0;
+        self.mem_write(addr, m & !self.registers.a)
+    }
+
+    /// CMOS: test-and-set bits. Z is set from `A & M`, then the bits set
+    /// in A are set in M.
+    #[allow(non_snake_case)]
+    fn TSB(&mut self) -> Result<()> {
+        let instr = self.instr.unwrap();
+        let addr = instr.write_target.unwrap();
+        let m = self.mem_read(addr)?;
+        self.registers.zero = (self.registers.a & m) == 0;
+        self.mem_write(addr, m | self.registers.a)
+    }
+
+    /// CMOS: unconditional branch (the 65C02's always-taken counterpart to
+    /// the NMOS conditional branches). Relative addressing decodes with no
+    /// `write_target`, so `mode_args` is the raw signed offset byte
+    /// (zero-extended into a `u16`), not a resolved address — resolve it
+    /// against `pc` the same way the NMOS branch handlers do.
+    #[allow(non_snake_case)]
+    fn BRA(&mut self) -> Result<()> {
+        let instr = self.instr.unwrap();
+        let offset = instr.mode_args as u8 as i8;
+        self.registers.pc = self.registers.pc.wrapping_add(offset as i16 as u16);
+        Ok(())
+    }
+
+    /// CMOS: push X.
+    #[allow(non_snake_case)]
+    fn PHX(&mut self) -> Result<()> {
+        self.push_stack(self.registers.x)
+    }
+
+    /// CMOS: push Y.
+    #[allow(non_snake_case)]
+    fn PHY(&mut self) -> Result<()> {
+        self.push_stack(self.registers.y)
+    }
+
+    /// CMOS: pull X, updating Z/N.
+    #[allow(non_snake_case)]
+    fn PLX(&mut self) -> Result<()> {
+        self.registers.x = self.pop_stack()?;
+        self.update_zero_and_negative_flags(self.registers.x);
+        Ok(())
+    }
+
+    /// CMOS: pull Y, updating Z/N.
+    #[allow(non_snake_case)]
+    fn PLY(&mut self) -> Result<()> {
+        self.registers.y = self.pop_stack()?;
+        self.update_zero_and_negative_flags(self.registers.y);
+        Ok(())
+    }
+
+    /// CMOS: `BIT #imm`. Unlike the memory forms of `BIT`, the immediate
+    /// form only ever updates Z (`A & M == 0`); N/V aren't meaningful
+    /// against a literal operand since there's no memory byte to read bits
+    /// 6/7 from.
+    #[allow(non_snake_case)]
+    fn BIT_IMMEDIATE(&mut self) -> Result<()> {
+        let instr = self.instr.unwrap();
+        self.registers.zero = (self.registers.a & instr.mode_args as u8) == 0;
+        Ok(())
+    }
+
+    /// CMOS: `INC A`, the accumulator form of `INC`.
+    #[allow(non_snake_case)]
+    fn INC_A(&mut self) -> Result<()> {
+        self.registers.a = self.registers.a.wrapping_add(1);
+        self.update_accumulator_flags();
+        Ok(())
+    }
+
+    /// CMOS: `DEC A`, the accumulator form of `DEC`.
+    #[allow(non_snake_case)]
+    fn DEC_A(&mut self) -> Result<()> {
+        self.registers.a = self.registers.a.wrapping_sub(1);
+        self.update_accumulator_flags();
+        Ok(())
+    }
+
+    /// Read an iNES image from the path given on the command line.
     #[allow(dead_code)]
-    fn load_image(self: &mut Self) {
+    fn load_image(self: &mut Self) -> Result<()> {
         let cli = Cli::from_args();
+        self.load_rom(cli.path)
+    }
 
-        let f = File::open(cli.path).expect("couldn't open file");
-        let f = BufReader::new(f);
-        println!("{}", f.capacity());
+    /// Render one line of `instr` in the nestest log format — `PC
+    /// HEXBYTES  DISASM  A:xx X:xx Y:xx P:xx SP:xx CYC:n` — so the output
+    /// can be compared against reference logs from the NES functional-test
+    /// ROM suites. The register fields match exactly; `CYC` leaves out
+    /// extra cycles (see `cycles`), so it drifts from the reference on
+    /// instructions that take them. The disassembly renders the operand for the addressing mode (`$00C5`,
+    /// `#$10`, `($20),Y`) but, unlike the reference logs, doesn't append
+    /// the resolved `@ addr = val` annotation indirect/indexed modes
+    /// carry, so a byte-for-byte diff against those lines will still show
+    /// a difference on that trailing annotation.
+    fn nestest_trace_line(&self, instr: &CpuInstruction, num_bytes: u16) -> String {
+        let pc = self.registers.pc;
+
+        let mut hex_bytes = String::new();
+        for offset in 0..num_bytes {
+            let byte = self.mem_read(pc.wrapping_add(offset)).unwrap_or(0);
+            hex_bytes.push_str(&format!("{:02X} ", byte));
+        }
+
+        let disasm = format!(
+            "{:?} {}",
+            instr.opcode,
+            self.disassemble_operand(instr)
+        );
+
+        format!(
+            "{:04X}  {:<9} {:<31} A:{:02X} X:{:02X} Y:{:02X} P:{:02X} SP:{:02X} CYC:{}",
+            pc,
+            hex_bytes.trim_end(),
+            disasm.trim_end(),
+            self.registers.a,
+            self.registers.x,
+            self.registers.y,
+            self.status_register_byte(false),
+            self.registers.sp,
+            self.cycles,
+        )
+    }
+
+    /// Render `instr`'s operand per its addressing mode, e.g. `$00C5` for
+    /// absolute, `#$10` for immediate, `($20),Y` for indirect-indexed.
+    fn disassemble_operand(&self, instr: &CpuInstruction) -> String {
+        match instr.address_mode {
+            AddressingMode::Immediate => format!("#${:02X}", instr.mode_args as u8),
+            AddressingMode::ZeroPage => format!("${:02X}", instr.mode_args as u8),
+            AddressingMode::ZeroPage_X => format!("${:02X},X", instr.mode_args as u8),
+            AddressingMode::ZeroPage_Y => format!("${:02X},Y", instr.mode_args as u8),
+            AddressingMode::Absolute => format!("${:04X}", instr.mode_args),
+            AddressingMode::Absolute_X => format!("${:04X},X", instr.mode_args),
+            AddressingMode::Absolute_Y => format!("${:04X},Y", instr.mode_args),
+            AddressingMode::Indirect_X => format!("(${:02X},X)", instr.mode_args as u8),
+            AddressingMode::Indirect_Y => format!("(${:02X}),Y", instr.mode_args as u8),
+            AddressingMode::NoneAddressing => String::new(),
+        }
     }
 }
@@ -0,0 +1,215 @@
+use std::cell::{Cell, RefCell};
+use std::collections::HashSet;
+use std::io::{self, Write};
+use std::marker::PhantomData;
+
+use anyhow::Result;
+
+use crate::cpu::cpu6502::Cpu6502;
+use crate::cpu::instruction::CpuInstruction;
+use crate::cpu::variant::Variant;
+use crate::mem::Mem;
+
+// reference: moa's Debugger (https://github.com/transistorfet/moa)
+
+/// Command-driven debugger attached to a [`Cpu6502`]: PC breakpoints,
+/// memory watchpoints, a trace-only mode, and a `step`/`continue`/
+/// `break <addr>`/`watch <addr>`/`mem <addr> <len>`/`regs` command loop.
+///
+/// `debug_instr` is called as `self.debugger.debug_instr(self, instr)` from
+/// `clocked`, which borrows the CPU and the debugger at the same time.
+/// Interior mutability (`Cell`/`RefCell`) lets this type track breakpoints,
+/// the last command, and repeat counts through a shared `&self`.
+#[derive(Debug)]
+pub struct CpuDebugger<T> {
+    breakpoints: RefCell<HashSet<u16>>,
+    watchpoints: RefCell<HashSet<u16>>,
+    /// When set, instructions are traced but never halt into the prompt.
+    trace_only: Cell<bool>,
+    /// The last command line entered, re-run when the user just presses
+    /// enter, mirroring moa's `last_command`/repeat handling.
+    last_command: RefCell<Option<String>>,
+    /// Address the most recent `mem_write` touched, so watchpoints can be
+    /// checked after the instruction that wrote them executes.
+    last_write: Cell<Option<u16>>,
+    /// Instructions still to execute before halting back into the prompt,
+    /// set by `step <n>` so a single command can step multiple instructions
+    /// at once. Zero when no step is in progress.
+    steps_remaining: Cell<u32>,
+    _marker: PhantomData<T>,
+}
+
+impl<T> Default for CpuDebugger<T> {
+    fn default() -> Self {
+        Self {
+            breakpoints: RefCell::new(HashSet::new()),
+            watchpoints: RefCell::new(HashSet::new()),
+            trace_only: Cell::new(false),
+            last_command: RefCell::new(None),
+            last_write: Cell::new(None),
+            steps_remaining: Cell::new(0),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T> CpuDebugger<T> {
+    pub fn add_breakpoint(&self, addr: u16) {
+        self.breakpoints.borrow_mut().insert(addr);
+    }
+
+    pub fn remove_breakpoint(&self, addr: u16) {
+        self.breakpoints.borrow_mut().remove(&addr);
+    }
+
+    pub fn add_watchpoint(&self, addr: u16) {
+        self.watchpoints.borrow_mut().insert(addr);
+    }
+
+    pub fn remove_watchpoint(&self, addr: u16) {
+        self.watchpoints.borrow_mut().remove(&addr);
+    }
+
+    pub fn set_trace_only(&self, trace_only: bool) {
+        self.trace_only.set(trace_only);
+    }
+
+    /// Record the address of the most recent `mem_write`, called from
+    /// `Cpu6502::mem_write` so watchpoints can be checked against it.
+    pub fn note_write(&self, addr: u16) {
+        self.last_write.set(Some(addr));
+    }
+
+    fn hit_breakpoint(&self, pc: u16) -> bool {
+        self.breakpoints.borrow().contains(&pc)
+    }
+
+    fn hit_watchpoint(&self) -> bool {
+        match self.last_write.get() {
+            Some(addr) => self.watchpoints.borrow().contains(&addr),
+            None => false,
+        }
+    }
+
+    /// Called once per instruction from `clocked`, before execution. With
+    /// `trace_only` set, prints a trace line and never halts. Otherwise
+    /// halts into the interactive prompt when a `step` finishes, `pc` hits a
+    /// breakpoint, or the last write hit a watchpoint.
+    pub fn debug_instr<V: Variant>(&self, cpu: &Cpu6502<V>, instr: CpuInstruction) -> Result<()> {
+        if self.trace_only.get() {
+            println!(
+                "{:04X}  {:?}  A:{:02X} X:{:02X} Y:{:02X} SP:{:02X}",
+                cpu.registers.pc,
+                instr.opcode,
+                cpu.registers.a,
+                cpu.registers.x,
+                cpu.registers.y,
+                cpu.registers.sp,
+            );
+            return Ok(());
+        }
+
+        let stepping = self.steps_remaining.get();
+        if stepping > 1 {
+            self.steps_remaining.set(stepping - 1);
+            return Ok(());
+        }
+        let step_done = stepping == 1;
+        self.steps_remaining.set(0);
+
+        // Consume `last_write` here regardless of which side of the `||`
+        // short-circuits, so a write to a watched address halts the prompt
+        // exactly once instead of re-triggering every instruction after it
+        // until some unrelated write overwrites `last_write`.
+        let watchpoint_hit = self.hit_watchpoint();
+        self.last_write.set(None);
+
+        if step_done || self.hit_breakpoint(cpu.registers.pc) || watchpoint_hit {
+            self.prompt(cpu)?;
+        }
+
+        Ok(())
+    }
+
+    /// Interactive command loop entered on a breakpoint/watchpoint hit.
+    /// Pressing enter with no input re-runs the last command instead of
+    /// doing nothing. The repeat count is parsed as moa does
+    /// (`args[1].parse::<u32>()`) and is used only by `step`, as the number
+    /// of instructions to step; other commands run once.
+    fn prompt<V: Variant>(&self, cpu: &Cpu6502<V>) -> Result<()> {
+        loop {
+            print!("debug> ");
+            io::stdout().flush()?;
+
+            let mut line = String::new();
+            io::stdin().read_line(&mut line)?;
+            let trimmed = line.trim();
+
+            let command = if trimmed.is_empty() {
+                self.last_command.borrow().clone()
+            } else {
+                Some(trimmed.to_string())
+            };
+            let Some(command) = command else {
+                continue;
+            };
+
+            let args: Vec<&str> = command.split_whitespace().collect();
+            let repeat = if args.len() > 1 {
+                args[1].parse::<u32>().unwrap_or(1)
+            } else {
+                1
+            };
+
+            match args.first().copied() {
+                Some("step") | Some("s") => {
+                    self.steps_remaining.set(repeat.max(1));
+                    *self.last_command.borrow_mut() = Some(command);
+                    return Ok(());
+                }
+                Some("continue") | Some("c") => {
+                    *self.last_command.borrow_mut() = Some(command);
+                    return Ok(());
+                }
+                Some("break") | Some("b") => {
+                    if let Some(addr) = args.get(1).and_then(|a| parse_addr(a)) {
+                        self.add_breakpoint(addr);
+                        println!("breakpoint set at {:04X}", addr);
+                    }
+                }
+                Some("watch") | Some("w") => {
+                    if let Some(addr) = args.get(1).and_then(|a| parse_addr(a)) {
+                        self.add_watchpoint(addr);
+                        println!("watchpoint set at {:04X}", addr);
+                    }
+                }
+                Some("mem") | Some("m") => {
+                    if let (Some(addr), Some(len)) = (
+                        args.get(1).and_then(|a| parse_addr(a)),
+                        args.get(2).and_then(|l| l.parse::<u16>().ok()),
+                    ) {
+                        for offset in 0..len {
+                            let byte = cpu.mem_read(addr.wrapping_add(offset)).unwrap_or(0);
+                            print!("{:02X} ", byte);
+                        }
+                        println!();
+                    }
+                }
+                Some("regs") | Some("r") => {
+                    cpu.print_register_status();
+                }
+                _ => {
+                    println!("unknown command: {command}");
+                    continue;
+                }
+            }
+
+            *self.last_command.borrow_mut() = Some(command);
+        }
+    }
+}
+
+fn parse_addr(arg: &str) -> Option<u16> {
+    let arg = arg.trim_start_matches("0x").trim_start_matches('$');
+    u16::from_str_radix(arg, 16).ok()
+}
@@ -0,0 +1,31 @@
+use std::fmt::Debug;
+
+// reference: https://www.nesdev.org/wiki/CPU#Chip_variations
+
+/// Selects NMOS-6502 vs 65C02 (CMOS) opcode behavior for [`Cpu6502`],
+/// modeled on how the `mos6502` crate parameterizes `CPU<M, V>` over a
+/// variant type instead of branching on a runtime flag.
+///
+/// [`Cpu6502`]: crate::cpu::cpu6502::Cpu6502
+pub trait Variant: Debug + Default {
+    /// Whether this variant decodes and executes the 65C02 opcode
+    /// extensions (`STZ`, `TRB`/`TSB`, `BRA`, `PHX`/`PHY`/`PLX`/`PLY`, ...).
+    const IS_CMOS: bool;
+}
+
+/// The original NMOS 6502 instruction set, as shipped in the NES.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Nmos6502;
+
+impl Variant for Nmos6502 {
+    const IS_CMOS: bool = false;
+}
+
+/// The WDC 65C02 instruction set: a superset of the NMOS 6502 adding a
+/// handful of new opcodes and fixing some of its undefined-opcode quirks.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Cmos6502;
+
+impl Variant for Cmos6502 {
+    const IS_CMOS: bool = true;
+}
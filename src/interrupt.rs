@@ -0,0 +1,29 @@
+// reference: https://www.nesdev.org/wiki/CPU_interrupts
+
+/// The four ways the 6502 can divert from normal instruction flow.
+///
+/// `Irq` is level-triggered and masked by `registers.interrupt_disabled`;
+/// `Nmi` is edge-triggered and unmaskable; `Reset` and `Brk` always run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Interrupt {
+    Reset,
+    Nmi,
+    Irq,
+    Brk,
+}
+
+impl Interrupt {
+    /// Address of the vector this interrupt loads the program counter from.
+    pub fn vector_address(self) -> u16 {
+        match self {
+            Interrupt::Nmi => 0xfffa,
+            Interrupt::Reset => 0xfffc,
+            Interrupt::Irq | Interrupt::Brk => 0xfffe,
+        }
+    }
+
+    /// Whether `registers.interrupt_disabled` masks this interrupt.
+    pub fn is_maskable(self) -> bool {
+        matches!(self, Interrupt::Irq)
+    }
+}
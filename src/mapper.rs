@@ -0,0 +1,81 @@
+use anyhow::{bail, Result};
+
+// reference: https://www.nesdev.org/wiki/Mapper
+
+/// A cartridge mapper: owns the PRG/CHR ROM (and any PRG RAM) that lives in
+/// the `$4020-$FFFF` cartridge address space and decides how CPU addresses
+/// in that range are routed onto it.
+///
+/// Real NES cartridges ship with a `Mapper` that can bank-switch, but a lot
+/// of them (including NROM) just mirror a fixed image straight through.
+pub trait Mapper: std::fmt::Debug {
+    fn mem_read(&self, addr: u16) -> Result<u8>;
+    fn mem_write(&mut self, addr: u16, data: u8) -> Result<()>;
+
+    /// Serialize this mapper's RAM-backed state for a save-state snapshot.
+    /// Defaults to empty for mappers with nothing but ROM.
+    fn save_state(&self) -> Vec<u8> {
+        Vec::new()
+    }
+
+    /// Restore state produced by `save_state`.
+    fn load_state(&mut self, _bytes: &[u8]) -> Result<()> {
+        Ok(())
+    }
+
+    /// PRG RAM contents (`$6000-$7FFF`), for battery-backed `.sav`
+    /// persistence. Mappers without PRG RAM return `None`.
+    fn prg_ram(&self) -> Option<&[u8]> {
+        None
+    }
+
+    /// Mutable counterpart of `prg_ram`, used to restore a loaded `.sav`.
+    fn prg_ram_mut(&mut self) -> Option<&mut [u8]> {
+        None
+    }
+}
+
+/// Placeholder mapper used before a cartridge has been loaded.
+///
+/// Backs the full cartridge address space with plain RAM so the CPU can
+/// still run hand-written test programs (see `Cpu6502::load_test_program`)
+/// without a ROM image.
+#[derive(Debug)]
+pub struct NullMapper {
+    ram: [u8; 0x10000],
+}
+
+impl Default for NullMapper {
+    fn default() -> Self {
+        Self {
+            ram: [0u8; 0x10000],
+        }
+    }
+}
+
+impl Mapper for NullMapper {
+    fn mem_read(&self, addr: u16) -> Result<u8> {
+        Ok(self.ram[addr as usize])
+    }
+
+    fn mem_write(&mut self, addr: u16, data: u8) -> Result<()> {
+        self.ram[addr as usize] = data;
+        Ok(())
+    }
+
+    fn save_state(&self) -> Vec<u8> {
+        self.ram.to_vec()
+    }
+
+    fn load_state(&mut self, bytes: &[u8]) -> Result<()> {
+        if bytes.len() != self.ram.len() {
+            bail!(
+                "corrupt save state: expected {} bytes of RAM, got {}",
+                self.ram.len(),
+                bytes.len()
+            );
+        }
+        self.ram.copy_from_slice(bytes);
+        Ok(())
+    }
+}